@@ -0,0 +1,305 @@
+use bytes::Bytes;
+use containerd_client::services::v1::container::Runtime;
+use containerd_client::services::v1::containers_client::ContainersClient;
+use containerd_client::services::v1::tasks_client::TasksClient;
+use containerd_client::services::v1::{
+    Container as ContainerProto, CreateContainerRequest, DeleteContainerRequest, GetContainerRequest,
+    GetRequest, ListContainersRequest,
+};
+use containerd_client::with_namespace;
+use futures_util::Stream;
+use oci_spec::runtime::Spec;
+use prost_types::Any;
+use tokio::sync::mpsc;
+use tonic::transport::Channel;
+
+use crate::snapshot::{self, MountedRootfs};
+use crate::spec::{self, SpecOptions};
+use crate::task::Task;
+
+/// containerd's built-in overlay-based snapshotter, and the default used by
+/// [`Containers`] unless overridden via [`Containers::snapshotter`].
+pub(crate) const DEFAULT_SNAPSHOTTER: &str = "overlayfs";
+
+/// Handle for creating and enumerating containers in a namespace.
+///
+/// Obtained via [`crate::Kappa::containers`].
+pub struct Containers {
+    pub(crate) channel: Channel,
+    pub(crate) namespace: String,
+    pub(crate) snapshotter: String,
+}
+
+impl Containers {
+    /// Use `snapshotter` (e.g. `"overlayfs"`, `"native"`, `"btrfs"`) instead
+    /// of `"overlayfs"` for containers created from here on.
+    pub fn snapshotter(mut self, snapshotter: impl Into<String>) -> Self {
+        self.snapshotter = snapshotter.into();
+        self
+    }
+
+    /// Create a container named `id` that runs `image` according to `spec`.
+    ///
+    /// Prepares an active snapshot keyed by `id` on top of `image`'s chain
+    /// id, since containerd resolves a container's rootfs through the
+    /// snapshot its record points to, not through `spec` alone.
+    pub async fn create(&self, id: &str, image: &str, spec: &Spec) -> anyhow::Result<Container> {
+        let parent = spec::chain_id(self.channel.clone(), &self.namespace, image).await?;
+        snapshot::prepare(
+            self.channel.clone(),
+            &self.namespace,
+            &self.snapshotter,
+            id,
+            &parent,
+        )
+        .await?;
+
+        let spec_bytes = serde_json::to_vec(spec)?;
+        let spec_any = Any {
+            type_url: "types.containerd.io/opencontainers/runtime-spec/1/Spec".to_string(),
+            value: spec_bytes,
+        };
+
+        let container = ContainerProto {
+            id: id.to_string(),
+            image: image.to_string(),
+            runtime: Some(Runtime {
+                name: "io.containerd.runc.v2".to_string(),
+                options: None,
+            }),
+            snapshotter: self.snapshotter.clone(),
+            snapshot_key: id.to_string(),
+            spec: Some(spec_any),
+            ..Default::default()
+        };
+
+        let mut client = ContainersClient::new(self.channel.clone());
+        let request = CreateContainerRequest {
+            container: Some(container),
+        };
+        client
+            .create(with_namespace!(request, &self.namespace))
+            .await?;
+
+        Ok(self.get(id))
+    }
+
+    /// Create a container named `id` that runs `image`, building its `Spec`
+    /// from the image's own entrypoint/cmd/env/cwd/user, merged with
+    /// `options`. Unlike [`Containers::create`], the caller doesn't have to
+    /// hand-write argv for an arbitrary image.
+    pub async fn create_from_image(
+        &self,
+        id: &str,
+        image: &str,
+        options: &SpecOptions,
+    ) -> anyhow::Result<Container> {
+        let built = spec::build(self.channel.clone(), &self.namespace, image, options).await?;
+        self.create(id, image, &built).await
+    }
+
+    /// Get a handle to the container named `id`, without checking that it exists.
+    pub fn get(&self, id: &str) -> Container {
+        Container {
+            channel: self.channel.clone(),
+            namespace: self.namespace.clone(),
+            id: id.to_string(),
+        }
+    }
+
+    /// List the ids of containers in this namespace.
+    pub async fn list(&self) -> anyhow::Result<Vec<String>> {
+        let mut client = ContainersClient::new(self.channel.clone());
+        let request = ListContainersRequest { filters: vec![] };
+        let response = client
+            .list(with_namespace!(request, &self.namespace))
+            .await?;
+        Ok(response
+            .into_inner()
+            .containers
+            .into_iter()
+            .map(|c| c.id)
+            .collect())
+    }
+}
+
+/// A single containerd container.
+///
+/// Obtained via [`Containers::get`] or [`Containers::create`].
+pub struct Container {
+    pub(crate) channel: Channel,
+    pub(crate) namespace: String,
+    pub(crate) id: String,
+}
+
+impl Container {
+    /// The container's id.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Handle to this container's task (its running process).
+    pub fn tasks(&self) -> Task {
+        Task {
+            channel: self.channel.clone(),
+            namespace: self.namespace.clone(),
+            container_id: self.id.clone(),
+        }
+    }
+
+    /// Delete this container and the snapshot `create`/`create_from_image`
+    /// prepared for it. Its task must already have been deleted.
+    pub async fn delete(&self) -> anyhow::Result<()> {
+        let (snapshotter, key) = self.snapshot_ref().await?;
+
+        let mut client = ContainersClient::new(self.channel.clone());
+        let request = DeleteContainerRequest {
+            id: self.id.clone(),
+        };
+        client
+            .delete(with_namespace!(request, &self.namespace))
+            .await?;
+
+        snapshot::remove(self.channel.clone(), &self.namespace, &snapshotter, &key).await?;
+        Ok(())
+    }
+
+    /// Resolve this container's actual `(snapshotter, snapshot_key)` from
+    /// its record, rather than assuming the snapshot is keyed by the
+    /// container id.
+    async fn snapshot_ref(&self) -> anyhow::Result<(String, String)> {
+        let mut client = ContainersClient::new(self.channel.clone());
+        let request = GetContainerRequest {
+            id: self.id.clone(),
+        };
+        let record = client
+            .get(with_namespace!(request, &self.namespace))
+            .await?
+            .into_inner()
+            .container
+            .ok_or_else(|| anyhow::anyhow!("container {} not found", self.id))?;
+
+        let snapshotter = if record.snapshotter.is_empty() {
+            DEFAULT_SNAPSHOTTER.to_string()
+        } else {
+            record.snapshotter
+        };
+        let key = if record.snapshot_key.is_empty() {
+            self.id.clone()
+        } else {
+            record.snapshot_key
+        };
+        Ok((snapshotter, key))
+    }
+
+    /// Refuse to mount this container's rootfs while its task is running:
+    /// a running task holds the snapshot's overlay workdir active, so
+    /// mounting it again typically fails with `EBUSY`.
+    async fn ensure_task_not_running(&self) -> anyhow::Result<()> {
+        let mut client = TasksClient::new(self.channel.clone());
+        let request = GetRequest {
+            container_id: self.id.clone(),
+            exec_id: String::new(),
+        };
+        match client.get(with_namespace!(request, &self.namespace)).await {
+            Ok(response) => {
+                // containerd's task.proto Status::RUNNING == 2.
+                let running = response
+                    .into_inner()
+                    .process
+                    .is_some_and(|process| process.status == 2);
+                if running {
+                    anyhow::bail!(
+                        "container {} has a running task; stop it before copying into or out of its rootfs",
+                        self.id
+                    );
+                }
+                Ok(())
+            }
+            Err(status) if status.code() == tonic::Code::NotFound => Ok(()),
+            Err(status) => Err(status.into()),
+        }
+    }
+
+    /// Unpack a tar archive into the container's rootfs at `path`.
+    ///
+    /// Resolves the container's snapshot mounts and extracts directly into
+    /// them, since containerd exposes the rootfs through the snapshotter
+    /// rather than a copy RPC.
+    pub async fn copy_into(&self, path: &str, tar_bytes: Vec<u8>) -> anyhow::Result<()> {
+        self.ensure_task_not_running().await?;
+        let (snapshotter, key) = self.snapshot_ref().await?;
+        let rootfs = MountedRootfs::new(self.channel.clone(), &self.namespace, &snapshotter, &key).await?;
+        let target = rootfs.path.join(path.trim_start_matches('/'));
+
+        tokio::task::spawn_blocking(move || -> anyhow::Result<()> {
+            std::fs::create_dir_all(&target)?;
+            tar::Archive::new(tar_bytes.as_slice()).unpack(&target)?;
+            Ok(())
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    /// Pack `path` inside the container's rootfs into a tar archive and
+    /// stream it out as it's built, rather than buffering the whole
+    /// archive in memory first. `path` may name either a directory or a
+    /// single file.
+    pub async fn copy_from(
+        &self,
+        path: &str,
+    ) -> anyhow::Result<impl Stream<Item = anyhow::Result<Bytes>>> {
+        self.ensure_task_not_running().await?;
+        let (snapshotter, key) = self.snapshot_ref().await?;
+        let rootfs = MountedRootfs::new(self.channel.clone(), &self.namespace, &snapshotter, &key).await?;
+        let source = rootfs.path.join(path.trim_start_matches('/'));
+        let is_dir = std::fs::metadata(&source)?.is_dir();
+
+        let (tx, rx) = mpsc::channel::<anyhow::Result<Bytes>>(16);
+
+        tokio::task::spawn_blocking(move || {
+            let result = (|| -> anyhow::Result<()> {
+                let mut builder = tar::Builder::new(ChannelWriter { tx: tx.clone() });
+                if is_dir {
+                    builder.append_dir_all(".", &source)?;
+                } else {
+                    let file_name = source
+                        .file_name()
+                        .ok_or_else(|| anyhow::anyhow!("path {} has no file name", source.display()))?;
+                    builder.append_path_with_name(&source, file_name)?;
+                }
+                builder.finish()?;
+                Ok(())
+            })();
+            // Keep the mount alive until the archive is fully written.
+            drop(rootfs);
+            if let Err(err) = result {
+                let _ = tx.blocking_send(Err(err));
+            }
+        });
+
+        Ok(futures_util::stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|chunk| (chunk, rx))
+        }))
+    }
+}
+
+/// Adapts a [`mpsc::Sender`] into a [`std::io::Write`] so `tar::Builder` can
+/// write an archive straight onto a channel instead of into a buffer.
+struct ChannelWriter {
+    tx: mpsc::Sender<anyhow::Result<Bytes>>,
+}
+
+impl std::io::Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.tx
+            .blocking_send(Ok(Bytes::copy_from_slice(buf)))
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::BrokenPipe, "receiver dropped"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+//! containerd's event bus.
+//!
+//! Instead of blocking on a `WaitRequest` per container, a supervisor can
+//! subscribe once and react to task exits, OOMs, and container/image
+//! lifecycle changes across every container in the namespace.
+
+use containerd_client::events::{
+    ContainerCreate, ContainerDelete, ImageCreate, ImageDelete, ImageUpdate, TaskExit, TaskOom,
+};
+use containerd_client::services::v1::events_client::EventsClient;
+use containerd_client::services::v1::SubscribeRequest;
+use containerd_client::with_namespace;
+use futures_util::{Stream, StreamExt};
+use prost::Message;
+use prost_types::{Any, Timestamp};
+use tonic::transport::Channel;
+
+/// A containerd lifecycle event, decoded from the envelope's `Any` payload.
+#[derive(Debug, Clone)]
+pub enum Event {
+    TaskExit(TaskExit),
+    TaskOom(TaskOom),
+    ContainerCreate(ContainerCreate),
+    ContainerDelete(ContainerDelete),
+    ImageCreate(ImageCreate),
+    ImageUpdate(ImageUpdate),
+    ImageDelete(ImageDelete),
+    /// An event type this crate doesn't decode yet, kept as its raw type URL.
+    Other(String),
+}
+
+/// An [`Event`] together with the envelope metadata containerd wraps it in.
+#[derive(Debug, Clone)]
+pub struct Envelope {
+    pub namespace: String,
+    pub topic: String,
+    pub timestamp: Option<Timestamp>,
+    pub event: Event,
+}
+
+fn decode(any: &Any) -> anyhow::Result<Event> {
+    let event = match any.type_url.as_str() {
+        "containerd.events.TaskExit" => Event::TaskExit(TaskExit::decode(any.value.as_slice())?),
+        "containerd.events.TaskOOM" => Event::TaskOom(TaskOom::decode(any.value.as_slice())?),
+        "containerd.events.ContainerCreate" => {
+            Event::ContainerCreate(ContainerCreate::decode(any.value.as_slice())?)
+        }
+        "containerd.events.ContainerDelete" => {
+            Event::ContainerDelete(ContainerDelete::decode(any.value.as_slice())?)
+        }
+        "containerd.events.ImageCreate" => {
+            Event::ImageCreate(ImageCreate::decode(any.value.as_slice())?)
+        }
+        "containerd.events.ImageUpdate" => {
+            Event::ImageUpdate(ImageUpdate::decode(any.value.as_slice())?)
+        }
+        "containerd.events.ImageDelete" => {
+            Event::ImageDelete(ImageDelete::decode(any.value.as_slice())?)
+        }
+        other => Event::Other(other.to_string()),
+    };
+    Ok(event)
+}
+
+/// Subscribe to the events service, optionally narrowed by `filters`
+/// (the same filter-expression style as `ListImagesRequest`).
+pub(crate) async fn subscribe(
+    channel: Channel,
+    namespace: String,
+    filters: Vec<String>,
+) -> anyhow::Result<impl Stream<Item = anyhow::Result<Envelope>>> {
+    let mut client = EventsClient::new(channel);
+    let request = SubscribeRequest { filters };
+    let stream = client
+        .subscribe(with_namespace!(request, &namespace))
+        .await?
+        .into_inner();
+
+    Ok(stream.map(|envelope| {
+        let envelope = envelope?;
+        let any = envelope.event.unwrap_or_default();
+        Ok(Envelope {
+            namespace: envelope.namespace,
+            topic: envelope.topic,
+            timestamp: envelope.timestamp,
+            event: decode(&any)?,
+        })
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn any_for(type_url: &str, message: impl Message) -> Any {
+        Any {
+            type_url: type_url.to_string(),
+            value: message.encode_to_vec(),
+        }
+    }
+
+    #[test]
+    fn decodes_known_type_urls() {
+        let any = any_for("containerd.events.TaskExit", TaskExit::default());
+        assert!(matches!(decode(&any).unwrap(), Event::TaskExit(_)));
+
+        let any = any_for("containerd.events.ContainerCreate", ContainerCreate::default());
+        assert!(matches!(decode(&any).unwrap(), Event::ContainerCreate(_)));
+    }
+
+    #[test]
+    fn unknown_type_url_falls_back_to_other() {
+        let any = Any {
+            type_url: "containerd.events.SomethingNew".to_string(),
+            value: vec![],
+        };
+        match decode(&any).unwrap() {
+            Event::Other(type_url) => assert_eq!(type_url, "containerd.events.SomethingNew"),
+            other => panic!("expected Event::Other, got {other:?}"),
+        }
+    }
+}
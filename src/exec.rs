@@ -0,0 +1,99 @@
+//! Running extra processes inside an already-started task.
+//!
+//! A [`Task`](crate::task::Task) only has the one process baked into its
+//! container's OCI spec. Debugging a live container (`/bin/sh` into it) or
+//! probing its health means issuing containerd's `ExecProcessRequest`
+//! against that same task and tracking the resulting process id
+//! independently, the way shiplift and bollard's `exec` endpoints do.
+
+use containerd_client::services::v1::tasks_client::TasksClient;
+use containerd_client::services::v1::{DeleteProcessRequest, ExecProcessRequest, StartRequest, WaitRequest};
+use containerd_client::with_namespace;
+use oci_spec::runtime::ProcessBuilder;
+use prost_types::Any;
+use tonic::transport::Channel;
+
+/// Options for starting an additional process inside a running task.
+///
+/// Passed to [`crate::task::Task::exec`].
+#[derive(Debug, Clone, Default)]
+pub struct ExecOptions {
+    pub args: Vec<String>,
+    pub env: Vec<String>,
+    pub cwd: Option<String>,
+    pub terminal: bool,
+    pub stdin: String,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// A process created via [`crate::task::Task::exec`], started and waited on
+/// independently of the task's main process.
+pub struct Exec {
+    pub(crate) channel: Channel,
+    pub(crate) namespace: String,
+    pub(crate) container_id: String,
+    pub(crate) exec_id: String,
+}
+
+impl Exec {
+    /// The exec id this process was created under, as passed to
+    /// [`crate::task::Task::exec`].
+    pub fn id(&self) -> &str {
+        &self.exec_id
+    }
+
+    /// Start the process running.
+    pub async fn start(&self) -> anyhow::Result<()> {
+        let mut client = TasksClient::new(self.channel.clone());
+        let request = StartRequest {
+            container_id: self.container_id.clone(),
+            exec_id: self.exec_id.clone(),
+        };
+        client
+            .start(with_namespace!(request, &self.namespace))
+            .await?;
+        Ok(())
+    }
+
+    /// Block until the process exits, returning its exit status.
+    pub async fn wait(&self) -> anyhow::Result<u32> {
+        let mut client = TasksClient::new(self.channel.clone());
+        let request = WaitRequest {
+            container_id: self.container_id.clone(),
+            exec_id: self.exec_id.clone(),
+        };
+        let response = client
+            .wait(with_namespace!(request, &self.namespace))
+            .await?;
+        Ok(response.into_inner().exit_status)
+    }
+
+    /// Delete the process. It must have already exited.
+    pub async fn delete(&self) -> anyhow::Result<()> {
+        let mut client = TasksClient::new(self.channel.clone());
+        let request = DeleteProcessRequest {
+            container_id: self.container_id.clone(),
+            exec_id: self.exec_id.clone(),
+        };
+        client
+            .delete_process(with_namespace!(request, &self.namespace))
+            .await?;
+        Ok(())
+    }
+}
+
+/// Build the OCI process spec `Any` for an exec request from `options`.
+pub(crate) fn process_spec(options: &ExecOptions) -> anyhow::Result<Any> {
+    let process = ProcessBuilder::default()
+        .args(options.args.clone())
+        .env(options.env.clone())
+        .cwd(options.cwd.clone().unwrap_or_else(|| "/".to_string()))
+        .terminal(options.terminal)
+        .build()?;
+
+    Ok(Any {
+        type_url: "types.containerd.io/opencontainers/runtime-spec/1/Process".to_string(),
+        value: serde_json::to_vec(&process)?,
+    })
+}
@@ -0,0 +1,204 @@
+use containerd_client::services::v1::images_client::ImagesClient;
+use containerd_client::services::v1::transfer_client::TransferClient;
+use containerd_client::services::v1::{ListImagesRequest, TransferOptions, TransferRequest};
+use containerd_client::types::transfer::{
+    AuthConfig, ImageStore, OciRegistry, RegistryResolver, UnpackConfiguration,
+};
+use containerd_client::types::Platform;
+use containerd_client::{to_any, with_namespace};
+use std::env::consts;
+use tonic::transport::Channel;
+use tracing::debug;
+
+/// Handle for pulling and listing images in a namespace.
+///
+/// Obtained via [`crate::Kappa::images`].
+pub struct Images {
+    pub(crate) channel: Channel,
+    pub(crate) namespace: String,
+}
+
+/// A single image entry returned by [`Images::list`].
+#[derive(Debug, Clone)]
+pub struct ImageInfo {
+    pub name: String,
+    pub digest: String,
+}
+
+/// Credentials for a private or rate-limited registry.
+///
+/// Passed to [`Images::pull_with_auth`]. Mirrors shiplift's `RegistryAuth`.
+#[derive(Debug, Clone)]
+pub enum RegistryAuth {
+    /// HTTP basic auth, e.g. a Docker Hub username/password.
+    Basic { username: String, password: String },
+    /// A bearer/identity token, e.g. an OAuth access token.
+    Token(String),
+}
+
+impl RegistryAuth {
+    /// Build credentials from `KAPPA_REGISTRY_USERNAME`/`KAPPA_REGISTRY_PASSWORD`,
+    /// falling back to `KAPPA_REGISTRY_TOKEN`. Returns `None` if neither is set
+    /// (e.g. via a `.env` file loaded by `dotenv`).
+    pub fn from_env() -> Option<Self> {
+        if let (Ok(username), Ok(password)) = (
+            std::env::var("KAPPA_REGISTRY_USERNAME"),
+            std::env::var("KAPPA_REGISTRY_PASSWORD"),
+        ) {
+            return Some(Self::Basic { username, password });
+        }
+        std::env::var("KAPPA_REGISTRY_TOKEN").ok().map(Self::Token)
+    }
+
+    fn into_auth_config(self) -> AuthConfig {
+        match self {
+            Self::Basic { username, password } => AuthConfig {
+                username,
+                password,
+                ..Default::default()
+            },
+            Self::Token(token) => AuthConfig {
+                identity_token: token,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl Images {
+    /// Pull `reference` (e.g. `docker.io/library/alpine:latest`) for the host's platform.
+    pub async fn pull(&self, reference: &str) -> anyhow::Result<()> {
+        self.pull_with_auth(reference, None, None).await
+    }
+
+    /// Pull `reference`, authenticating against its registry with `auth` if
+    /// given, and targeting `platform` (an `os/arch` string like
+    /// `linux/arm64`) instead of the host's platform if given.
+    pub async fn pull_with_auth(
+        &self,
+        reference: &str,
+        auth: Option<RegistryAuth>,
+        platform: Option<&str>,
+    ) -> anyhow::Result<()> {
+        let mut client = TransferClient::new(self.channel.clone());
+
+        let (os, arch) = match platform.and_then(|p| p.split_once('/')) {
+            Some((os, arch)) => (os.to_string(), arch.to_string()),
+            None => {
+                let arch = match consts::ARCH {
+                    "x86_64" => "amd64",
+                    "aarch64" => "arm64",
+                    _ => consts::ARCH,
+                };
+                ("linux".to_string(), arch.to_string())
+            }
+        };
+
+        let resolver = RegistryResolver {
+            auth: auth.map(RegistryAuth::into_auth_config),
+            ..Default::default()
+        };
+
+        let source = OciRegistry {
+            reference: reference.to_string(),
+            resolver: Some(resolver),
+        };
+
+        let platform = Platform {
+            os,
+            architecture: arch,
+            variant: "".to_string(),
+            os_version: "".to_string(),
+        };
+
+        let destination = ImageStore {
+            name: reference.to_string(),
+            platforms: vec![platform.clone()],
+            unpacks: vec![UnpackConfiguration {
+                platform: Some(platform),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let request = TransferRequest {
+            source: Some(to_any(&source)),
+            destination: Some(to_any(&destination)),
+            options: Some(TransferOptions::default()),
+        };
+
+        client
+            .transfer(with_namespace!(request, &self.namespace))
+            .await?;
+        debug!(reference, "pulled image");
+        Ok(())
+    }
+
+    /// List the images known to this namespace.
+    pub async fn list(&self) -> anyhow::Result<Vec<ImageInfo>> {
+        let mut client = ImagesClient::new(self.channel.clone());
+        let request = ListImagesRequest { filters: vec![] };
+        let response = client
+            .list(with_namespace!(request, &self.namespace))
+            .await?;
+
+        Ok(response
+            .into_inner()
+            .images
+            .into_iter()
+            .map(|image| ImageInfo {
+                digest: image.target.map(|t| t.digest).unwrap_or_default(),
+                name: image.name,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `std::env::var` is process-global, so these run on one thread to avoid
+    // racing each other.
+    #[test]
+    fn registry_auth_from_env() {
+        std::env::remove_var("KAPPA_REGISTRY_USERNAME");
+        std::env::remove_var("KAPPA_REGISTRY_PASSWORD");
+        std::env::remove_var("KAPPA_REGISTRY_TOKEN");
+        assert!(RegistryAuth::from_env().is_none());
+
+        std::env::set_var("KAPPA_REGISTRY_TOKEN", "a-token");
+        assert!(matches!(RegistryAuth::from_env(), Some(RegistryAuth::Token(t)) if t == "a-token"));
+        std::env::remove_var("KAPPA_REGISTRY_TOKEN");
+
+        std::env::set_var("KAPPA_REGISTRY_USERNAME", "alice");
+        std::env::set_var("KAPPA_REGISTRY_PASSWORD", "hunter2");
+        assert!(matches!(
+            RegistryAuth::from_env(),
+            Some(RegistryAuth::Basic { username, password })
+                if username == "alice" && password == "hunter2"
+        ));
+        std::env::remove_var("KAPPA_REGISTRY_USERNAME");
+        std::env::remove_var("KAPPA_REGISTRY_PASSWORD");
+    }
+
+    #[test]
+    fn basic_auth_into_config() {
+        let auth = RegistryAuth::Basic {
+            username: "alice".to_string(),
+            password: "hunter2".to_string(),
+        };
+        let config = auth.into_auth_config();
+        assert_eq!(config.username, "alice");
+        assert_eq!(config.password, "hunter2");
+        assert!(config.identity_token.is_empty());
+    }
+
+    #[test]
+    fn token_auth_into_config() {
+        let auth = RegistryAuth::Token("a-token".to_string());
+        let config = auth.into_auth_config();
+        assert_eq!(config.identity_token, "a-token");
+        assert!(config.username.is_empty());
+    }
+}
@@ -0,0 +1,106 @@
+//! Kappa is a small async client for containerd's gRPC API.
+//!
+//! A [`Kappa`] owns a single shared channel and namespace; everything else
+//! is a typed handle borrowed off of it, mirroring the way shiplift splits
+//! a Docker connection into `Images`, `Containers`, and friends:
+//!
+//! ```no_run
+//! # async fn run() -> anyhow::Result<()> {
+//! # let spec = oci_spec::runtime::Spec::default();
+//! let kappa = kappa_v2::Kappa::connect().await?;
+//! kappa.images().pull("docker.io/library/alpine:latest").await?;
+//! let container = kappa
+//!     .containers()
+//!     .create("my-container", "docker.io/library/alpine:latest", &spec)
+//!     .await?;
+//! let task = container.tasks();
+//! let _output = task.create_attached(false).await?;
+//! task.start().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod container;
+pub mod events;
+pub mod exec;
+pub mod images;
+mod snapshot;
+pub mod spec;
+pub mod task;
+pub mod tty;
+
+use containerd_client as client;
+use futures_util::Stream;
+use tonic::transport::Channel;
+
+pub use container::{Container, Containers};
+pub use events::{Envelope, Event};
+pub use exec::{Exec, ExecOptions};
+pub use images::{Images, RegistryAuth};
+pub use spec::SpecOptions;
+pub use task::Task;
+pub use tty::TtyChunk;
+
+const DEFAULT_SOCKET: &str = "/run/containerd/containerd.sock";
+const DEFAULT_NAMESPACE: &str = "default";
+
+/// Entry point into the containerd API.
+///
+/// Holds the shared gRPC [`Channel`] and namespace that every handle
+/// (`Images`, `Containers`, `Container`, `Task`) clones instead of
+/// reconnecting on its own.
+#[derive(Clone)]
+pub struct Kappa {
+    channel: Channel,
+    namespace: String,
+}
+
+impl Kappa {
+    /// Connect to containerd's default socket (`/run/containerd/containerd.sock`)
+    /// using the `"default"` namespace.
+    pub async fn connect() -> anyhow::Result<Self> {
+        Self::connect_to(DEFAULT_SOCKET).await
+    }
+
+    /// Connect to containerd over a specific socket path.
+    pub async fn connect_to(socket: &str) -> anyhow::Result<Self> {
+        let channel = client::connect(socket).await?;
+        Ok(Self {
+            channel,
+            namespace: DEFAULT_NAMESPACE.to_string(),
+        })
+    }
+
+    /// Use `namespace` instead of `"default"` for subsequent calls.
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = namespace.into();
+        self
+    }
+
+    /// Handle for pulling and listing images.
+    pub fn images(&self) -> Images {
+        Images {
+            channel: self.channel.clone(),
+            namespace: self.namespace.clone(),
+        }
+    }
+
+    /// Handle for creating and looking up containers.
+    pub fn containers(&self) -> Containers {
+        Containers {
+            channel: self.channel.clone(),
+            namespace: self.namespace.clone(),
+            snapshotter: container::DEFAULT_SNAPSHOTTER.to_string(),
+        }
+    }
+
+    /// Subscribe to containerd's lifecycle event stream: task exits and
+    /// OOMs, container create/delete, image create/update/delete. Pass an
+    /// empty `filters` list to receive everything in this namespace.
+    pub async fn events(
+        &self,
+        filters: Vec<String>,
+    ) -> anyhow::Result<impl Stream<Item = anyhow::Result<Envelope>>> {
+        events::subscribe(self.channel.clone(), self.namespace.clone(), filters).await
+    }
+}
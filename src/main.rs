@@ -1,29 +1,67 @@
-use containerd_client as client;
-use containerd_client::services::v1::container::Runtime;
-
-use containerd_client::services::v1::containers_client::ContainersClient;
-use containerd_client::services::v1::images_client::ImagesClient;
-use containerd_client::services::v1::tasks_client::TasksClient;
-use containerd_client::services::v1::transfer_client::TransferClient;
-use containerd_client::services::v1::{
-    Container, CreateContainerRequest, ListImagesRequest, TransferOptions, TransferRequest,
-};
-use containerd_client::services::v1::{
-    CreateTaskRequest, DeleteContainerRequest, DeleteTaskRequest, StartRequest, WaitRequest,
-};
-use containerd_client::types::transfer::{ImageStore, OciRegistry, UnpackConfiguration};
-use containerd_client::types::Platform;
-use prost_types::Any;
-//use containerd_client::{connect, services::v1::version_client::VersionClient};
-use containerd_client::{to_any, with_namespace};
+use clap::{Parser, Subcommand};
 use dotenv::dotenv;
-use oci_spec::runtime::{ProcessBuilder, RootBuilder, Spec, SpecBuilder};
-use std::env::consts;
+use futures_util::StreamExt;
+use kappa_v2::{ExecOptions, Kappa, RegistryAuth, SpecOptions, TtyChunk};
 use std::fs::{self, File};
-use tracing::{debug, info};
+use std::path::PathBuf;
+use tracing::info;
 use tracing_subscriber::EnvFilter;
 
-use tonic::Request;
+/// A small CLI for containerd, built on the `kappa_v2` library.
+#[derive(Parser)]
+#[command(name = "kappa")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+
+    /// containerd namespace to operate in.
+    #[arg(long, global = true, default_value = "default")]
+    namespace: String,
+
+    /// Path to the containerd socket.
+    #[arg(long, global = true, default_value = "/run/containerd/containerd.sock")]
+    socket: String,
+
+    /// Target platform as `os/arch` (e.g. `linux/arm64`). Defaults to the host platform.
+    #[arg(long, global = true)]
+    platform: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Pull an image.
+    Pull {
+        /// Image reference, e.g. `docker.io/library/alpine:latest`.
+        reference: String,
+    },
+    /// Pull (if needed) and run a container from an image.
+    Run {
+        /// Image reference, e.g. `docker.io/library/alpine:latest`.
+        reference: String,
+        /// Container id to create. Defaults to a name derived from the reference.
+        #[arg(long)]
+        id: Option<String>,
+        /// Command to run in the container, e.g. `-- /bin/sh -c 'echo hi'`.
+        #[arg(trailing_var_arg = true)]
+        cmd: Vec<String>,
+    },
+    /// Run an additional process inside an already-running container.
+    Exec {
+        /// Id of the running container to exec into.
+        id: String,
+        /// Command to run, e.g. `-- /bin/sh`.
+        #[arg(trailing_var_arg = true)]
+        cmd: Vec<String>,
+    },
+    /// List containers.
+    Ls,
+    /// Remove a container and its task. A running task is not stopped first.
+    Rm {
+        /// Id of the container to remove.
+        id: String,
+    },
+}
+
 fn main() -> anyhow::Result<()> {
     dotenv().ok();
     let subscriber = tracing_subscriber::fmt()
@@ -33,145 +71,16 @@ fn main() -> anyhow::Result<()> {
     tracing::subscriber::set_global_default(subscriber)?;
     info!("Setup subscriber for logging");
 
+    let cli = Cli::parse();
     let rt = tokio::runtime::Runtime::new().unwrap();
-    rt.block_on(pull_image())?;
-    info!("Pulled images");
-    rt.block_on(list_images())?;
-    info!("Listed images");
-    rt.block_on(create_container_from_image())?;
-    info!("Created container from image");
-
-    rt.block_on(run_container())?;
-    info!("Ran container from image");
-    Ok(())
-}
-fn create_container_spec() -> anyhow::Result<Spec> {
-    let spec = SpecBuilder::default()
-        .process(
-            ProcessBuilder::default()
-                .args(vec![
-                    "/bin/sh".to_string(),
-                    "-c".to_string(),
-                    "echo 'Hello'".to_string(),
-                ])
-                .build()?,
-        )
-        .root(
-            RootBuilder::default()
-                .path("rootfs")
-                .readonly(false)
-                .build()?,
-        )
-        .build()?;
-
-    Ok(spec)
-}
-
-async fn pull_image() -> anyhow::Result<()> {
-    let channel = client::connect("/run/containerd/containerd.sock").await?;
-    debug!("Connected to channel");
-    let mut client = TransferClient::new(channel);
-
-    // Setup platform info
-    let arch = match consts::ARCH {
-        "x86_64" => "amd64",
-        "aarch64" => "arm64",
-        _ => consts::ARCH,
-    };
-
-    // Configure source (Docker registry)
-    let source = OciRegistry {
-        reference: "docker.io/library/alpine:latest".to_string(),
-        resolver: Default::default(),
-    };
-
-    // Configure platform
-    let platform = Platform {
-        os: "linux".to_string(),
-        architecture: arch.to_string(),
-        variant: "".to_string(),
-        os_version: "".to_string(),
-    };
-
-    // Configure destination
-    let destination = ImageStore {
-        name: "docker.io/library/alpine:latest".to_string(),
-        platforms: vec![platform.clone()],
-        unpacks: vec![UnpackConfiguration {
-            platform: Some(platform),
-            ..Default::default()
-        }],
-        ..Default::default()
-    };
-
-    // Execute transfer
-    let request = TransferRequest {
-        source: Some(to_any(&source)),
-        destination: Some(to_any(&destination)),
-        options: Some(TransferOptions::default()),
-    };
-
-    client.transfer(with_namespace!(request, "default")).await?;
-    Ok(())
+    rt.block_on(run(cli))
 }
-async fn list_images() -> anyhow::Result<()> {
-    let channel = client::connect("/run/containerd/containerd.sock").await?;
-    let mut client = ImagesClient::new(channel);
 
-    let request = ListImagesRequest { filters: vec![] };
-
-    let response = client.list(with_namespace!(request, "default")).await?;
-
-    for image in response.get_ref().images.iter() {
-        info!(
-            "Image: {} ({})",
-            image.name,
-            image.target.as_ref().unwrap().digest
-        );
-    }
-
-    Ok(())
-}
-
-async fn create_container_from_image() -> anyhow::Result<()> {
-    let channel = client::connect("/run/containerd/containerd.sock").await?;
-    let mut containers_client = ContainersClient::new(channel);
-
-    // Create spec
-    let spec = create_container_spec()?;
-    let spec_bytes = serde_json::to_vec(&spec)?;
-    let spec_any = Any {
-        type_url: "types.containerd.io/opencontainers/runtime-spec/1/Spec".to_string(),
-        value: spec_bytes,
-    };
-
-    let container = Container {
-        id: "my-alpine-container".to_string(),
-        image: "docker.io/library/alpine:latest".to_string(),
-        runtime: Some(Runtime {
-            name: "io.containerd.runc.v2".to_string(),
-            options: None,
-        }),
-        spec: Some(spec_any),
-        ..Default::default()
-    };
-
-    let create_req = CreateContainerRequest {
-        container: Some(container),
-    };
-
-    let response = containers_client
-        .create(with_namespace!(create_req, "default"))
-        .await?;
-    println!("Container created {:?}", response);
-    Ok(())
-}
-async fn run_container() -> anyhow::Result<()> {
-    let channel = client::connect("/run/containerd/containerd.sock").await?;
-    let mut tasks_client = TasksClient::new(channel.clone());
-
-    // Create temporary directory for container I/O
-    let tmp = std::env::temp_dir().join("containerd-client-test");
+/// Create fresh stdio files for a task under a per-container temp directory.
+fn io_files(container_id: &str) -> anyhow::Result<(PathBuf, PathBuf, PathBuf, PathBuf)> {
+    let tmp = std::env::temp_dir()
+        .join("kappa-cli")
+        .join(container_id);
     fs::create_dir_all(&tmp)?;
     let stdin = tmp.join("stdin");
     let stdout = tmp.join("stdout");
@@ -179,72 +88,98 @@ async fn run_container() -> anyhow::Result<()> {
     File::create(&stdin)?;
     File::create(&stdout)?;
     File::create(&stderr)?;
+    Ok((tmp, stdin, stdout, stderr))
+}
 
-    // Create the task
-    let create_task_request = CreateTaskRequest {
-        container_id: "my-alpine-container".to_string(),
-        stdin: stdin.to_str().unwrap().to_string(),
-        stdout: stdout.to_str().unwrap().to_string(),
-        stderr: stderr.to_str().unwrap().to_string(),
-        terminal: false,
-        ..Default::default()
-    };
-
-    let _task = tasks_client
-        .create(with_namespace!(create_task_request, "default"))
-        .await?;
-    println!("Task created");
-
-    // Start the task
-    let start_request = StartRequest {
-        container_id: "my-alpine-container".to_string(),
-        ..Default::default()
-    };
-    tasks_client
-        .start(with_namespace!(start_request, "default"))
-        .await?;
-    println!("Task started");
-
-    // Wait for task completion
-    let wait_request = WaitRequest {
-        container_id: "my-alpine-container".to_string(),
-        ..Default::default()
-    };
-    let wait_response = tasks_client
-        .wait(with_namespace!(wait_request, "default"))
-        .await?;
-
-    // Print task output
-    let output = fs::read_to_string(stdout)?;
-    println!("Container output: {}", output);
-    println!(
-        "Task exited with status: {}",
-        wait_response.into_inner().exit_status
-    );
-
-    // Cleanup
-    // Delete the task
-    let delete_task_request = DeleteTaskRequest {
-        container_id: "my-alpine-container".to_string(),
-        ..Default::default()
-    };
-    tasks_client
-        .delete(with_namespace!(delete_task_request, "default"))
-        .await?;
-    println!("Task deleted");
-
-    // Delete the container
-    let mut containers_client = ContainersClient::new(channel);
-    let delete_container_request = DeleteContainerRequest {
-        id: "my-alpine-container".to_string(),
-    };
-    containers_client
-        .delete(with_namespace!(delete_container_request, "default"))
-        .await?;
-    println!("Container deleted");
-
-    // Cleanup temporary files
-    fs::remove_dir_all(tmp)?;
+async fn run(cli: Cli) -> anyhow::Result<()> {
+    let kappa = Kappa::connect_to(&cli.socket).await?.namespace(cli.namespace);
+
+    match cli.command {
+        Command::Pull { reference } => {
+            kappa
+                .images()
+                .pull_with_auth(&reference, RegistryAuth::from_env(), cli.platform.as_deref())
+                .await?;
+            println!("Pulled {reference}");
+        }
+        Command::Run { reference, id, cmd } => {
+            let id = id.unwrap_or_else(|| reference.replace(['/', ':'], "-"));
+
+            kappa
+                .images()
+                .pull_with_auth(&reference, RegistryAuth::from_env(), cli.platform.as_deref())
+                .await?;
+
+            let options = SpecOptions {
+                args: (!cmd.is_empty()).then_some(cmd),
+                ..Default::default()
+            };
+            let container = kappa
+                .containers()
+                .create_from_image(&id, &reference, &options)
+                .await?;
+
+            let task = container.tasks();
+            let mut output = task.create_attached(false).await?;
+            task.start().await?;
+
+            let printer = tokio::spawn(async move {
+                while let Some(chunk) = output.next().await {
+                    match chunk {
+                        Ok(TtyChunk::StdOut(bytes)) => print!("{}", String::from_utf8_lossy(&bytes)),
+                        Ok(TtyChunk::StdErr(bytes)) => eprint!("{}", String::from_utf8_lossy(&bytes)),
+                        Err(err) => {
+                            eprintln!("error reading task output: {err}");
+                            break;
+                        }
+                    }
+                }
+            });
+
+            let exit_status = task.wait().await?;
+            printer.await?;
+            println!("Task exited with status: {exit_status}");
+
+            task.delete().await?;
+            container.delete().await?;
+            fs::remove_dir_all(std::env::temp_dir().join("kappa-fifos").join(&id)).ok();
+        }
+        Command::Exec { id, cmd } => {
+            let container = kappa.containers().get(&id);
+            let (tmp, stdin, stdout, stderr) = io_files(&format!("{id}-exec"))?;
+
+            let exec = container.tasks().exec(
+                "exec",
+                ExecOptions {
+                    args: cmd,
+                    stdin: stdin.to_str().unwrap().to_string(),
+                    stdout: stdout.to_str().unwrap().to_string(),
+                    stderr: stderr.to_str().unwrap().to_string(),
+                    ..Default::default()
+                },
+            )
+            .await?;
+            exec.start().await?;
+            let exit_status = exec.wait().await?;
+
+            print!("{}", fs::read_to_string(&stdout)?);
+            println!("Exec exited with status: {exit_status}");
+
+            exec.delete().await?;
+            fs::remove_dir_all(tmp)?;
+        }
+        Command::Ls => {
+            for id in kappa.containers().list().await? {
+                println!("{id}");
+            }
+        }
+        Command::Rm { id } => {
+            let container = kappa.containers().get(&id);
+            container.tasks().delete().await.ok();
+            container.delete().await?;
+            println!("Removed {id}");
+        }
+    }
 
     Ok(())
 }
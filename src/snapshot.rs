@@ -0,0 +1,123 @@
+//! Resolving a container's rootfs through the snapshotter.
+//!
+//! containerd doesn't expose a copy-in/copy-out RPC; the rootfs is only
+//! reachable by asking the snapshots service for the mounts backing a
+//! container's snapshot and mounting them ourselves, the way `ctr` does
+//! for `ctr snapshot mounts`. `Containers::create` prepares that snapshot
+//! (keyed by the container id) up front so it exists by the time a caller
+//! asks to copy into or out of it.
+
+use containerd_client::services::v1::snapshots_client::SnapshotsClient;
+use containerd_client::services::v1::{MountsRequest, PrepareSnapshotRequest, RemoveSnapshotRequest};
+use containerd_client::with_namespace;
+use nix::mount::{mount, umount, MsFlags};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tonic::transport::Channel;
+
+/// Prepare an active snapshot named `key` on top of `parent` (a chain id,
+/// e.g. `sha256:...`), so `key` can later be mounted via [`MountedRootfs`].
+///
+/// Tolerates `key` already existing: `Containers::create` reuses the
+/// container id as the snapshot key, so re-running with the same id after
+/// a failed or skipped `Container::delete` would otherwise fail here
+/// instead of at the container-create call where it belongs.
+pub(crate) async fn prepare(
+    channel: Channel,
+    namespace: &str,
+    snapshotter: &str,
+    key: &str,
+    parent: &str,
+) -> anyhow::Result<()> {
+    let mut client = SnapshotsClient::new(channel);
+    let request = PrepareSnapshotRequest {
+        snapshotter: snapshotter.to_string(),
+        key: key.to_string(),
+        parent: parent.to_string(),
+        labels: HashMap::new(),
+    };
+    match client.prepare(with_namespace!(request, namespace)).await {
+        Ok(_) => Ok(()),
+        Err(status) if status.code() == tonic::Code::AlreadyExists => Ok(()),
+        Err(status) => Err(status.into()),
+    }
+}
+
+/// Remove the snapshot named `key`, e.g. once its container has been
+/// deleted. Ignores a snapshot that's already gone.
+pub(crate) async fn remove(
+    channel: Channel,
+    namespace: &str,
+    snapshotter: &str,
+    key: &str,
+) -> anyhow::Result<()> {
+    let mut client = SnapshotsClient::new(channel);
+    let request = RemoveSnapshotRequest {
+        snapshotter: snapshotter.to_string(),
+        key: key.to_string(),
+    };
+    match client.remove(with_namespace!(request, namespace)).await {
+        Ok(_) => Ok(()),
+        Err(status) if status.code() == tonic::Code::NotFound => Ok(()),
+        Err(status) => Err(status.into()),
+    }
+}
+
+/// A container's rootfs, mounted at a temporary directory for as long as
+/// this guard lives. Unmounted and cleaned up on drop.
+///
+/// Mounting a snapshot that a running task already holds active (its
+/// overlay workdir in use) typically fails with `EBUSY`; stop the
+/// container's task before copying into or out of its rootfs.
+pub(crate) struct MountedRootfs {
+    pub(crate) path: PathBuf,
+}
+
+impl MountedRootfs {
+    /// Resolve `key`'s mounts under `snapshotter` and mount them at a fresh
+    /// temporary directory.
+    pub(crate) async fn new(
+        channel: Channel,
+        namespace: &str,
+        snapshotter: &str,
+        key: &str,
+    ) -> anyhow::Result<Self> {
+        let mut client = SnapshotsClient::new(channel);
+        let request = MountsRequest {
+            snapshotter: snapshotter.to_string(),
+            key: key.to_string(),
+        };
+        let response = client
+            .mounts(with_namespace!(request, namespace))
+            .await?;
+
+        let path = std::env::temp_dir().join("kappa-mounts").join(key);
+        std::fs::create_dir_all(&path)?;
+
+        for m in response.into_inner().mounts {
+            let options = m.options.join(",");
+            mount(
+                Some(m.source.as_str()),
+                &path,
+                Some(m.r#type.as_str()),
+                MsFlags::empty(),
+                Some(options.as_str()),
+            )
+            .map_err(|err| {
+                anyhow::anyhow!(
+                    "failed to mount snapshot {key:?} (a running task holding it active \
+                     typically causes this; stop the task first): {err}"
+                )
+            })?;
+        }
+
+        Ok(Self { path })
+    }
+}
+
+impl Drop for MountedRootfs {
+    fn drop(&mut self) {
+        let _ = umount(&self.path);
+        let _ = std::fs::remove_dir(&self.path);
+    }
+}
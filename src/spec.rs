@@ -0,0 +1,218 @@
+//! Building a runtime [`Spec`] from an image's own OCI config.
+//!
+//! `Containers::create` takes a caller-built `Spec` directly; this module
+//! is what feeds it one that actually reflects the pulled image
+//! (entrypoint, cmd, env, cwd, user) instead of a hardcoded process,
+//! merging in caller overrides the way bollard's `Config` composes with
+//! runtime defaults.
+
+use containerd_client::services::v1::content_client::ContentClient;
+use containerd_client::services::v1::images_client::ImagesClient;
+use containerd_client::services::v1::{GetImageRequest, ReadContentRequest};
+use containerd_client::with_namespace;
+use oci_spec::image::{ImageConfiguration, ImageIndex, ImageManifest};
+use oci_spec::runtime::{ProcessBuilder, RootBuilder, Spec, SpecBuilder};
+use sha2::{Digest, Sha256};
+use std::env::consts;
+use tonic::transport::Channel;
+
+/// Caller overrides layered on top of an image's own config when building a
+/// container's [`Spec`]. Unset fields fall back to the image's defaults.
+#[derive(Debug, Clone, Default)]
+pub struct SpecOptions {
+    /// Replace the image's entrypoint + cmd entirely.
+    pub args: Option<Vec<String>>,
+    /// Extra environment variables, appended after the image's own `Env`.
+    pub env: Vec<String>,
+    /// Working directory override.
+    pub cwd: Option<String>,
+}
+
+/// Build a runtime [`Spec`] for `reference`, reading its image config from
+/// the content store and merging in `options`.
+pub(crate) async fn build(
+    channel: Channel,
+    namespace: &str,
+    reference: &str,
+    options: &SpecOptions,
+) -> anyhow::Result<Spec> {
+    let configuration = image_configuration(channel, namespace, reference).await?;
+    let config = configuration.config().clone().unwrap_or_default();
+
+    let args = options.args.clone().unwrap_or_else(|| {
+        let mut args = config.entrypoint().clone().unwrap_or_default();
+        args.extend(config.cmd().clone().unwrap_or_default());
+        if args.is_empty() {
+            args.push("/bin/sh".to_string());
+        }
+        args
+    });
+
+    let mut env = config.env().clone().unwrap_or_default();
+    env.extend(options.env.clone());
+
+    let cwd = options
+        .cwd
+        .clone()
+        .or_else(|| config.working_dir().clone())
+        .filter(|cwd| !cwd.is_empty())
+        .unwrap_or_else(|| "/".to_string());
+
+    let mut process = ProcessBuilder::default();
+    process.args(args).env(env).cwd(cwd);
+    if let Some(user) = numeric_user(config.user().as_deref()) {
+        process.user(user);
+    }
+
+    let spec = SpecBuilder::default()
+        .process(process.build()?)
+        .root(
+            RootBuilder::default()
+                .path("rootfs")
+                .readonly(false)
+                .build()?,
+        )
+        .build()?;
+
+    Ok(spec)
+}
+
+/// Compute `reference`'s chain id: the rolling hash of its layers' diff ids,
+/// used as the `parent` of the active snapshot containerd prepares for a
+/// new container (see `Containers::create`).
+pub(crate) async fn chain_id(channel: Channel, namespace: &str, reference: &str) -> anyhow::Result<String> {
+    let configuration = image_configuration(channel, namespace, reference).await?;
+    let diff_ids = configuration.rootfs().diff_ids();
+
+    let mut chain: Option<String> = None;
+    for diff_id in diff_ids {
+        chain = Some(match chain {
+            None => diff_id.clone(),
+            Some(parent) => {
+                let digest = Sha256::digest(format!("{parent} {diff_id}").as_bytes());
+                format!("sha256:{digest:x}")
+            }
+        });
+    }
+    chain.ok_or_else(|| anyhow::anyhow!("image {reference} has no layers"))
+}
+
+/// Fetch and parse `reference`'s full OCI image configuration.
+async fn image_configuration(
+    channel: Channel,
+    namespace: &str,
+    reference: &str,
+) -> anyhow::Result<ImageConfiguration> {
+    let mut images = ImagesClient::new(channel.clone());
+    let request = GetImageRequest {
+        name: reference.to_string(),
+    };
+    let image = images
+        .get(with_namespace!(request, namespace))
+        .await?
+        .into_inner()
+        .image
+        .ok_or_else(|| anyhow::anyhow!("image {reference} not found"))?;
+    let target = image
+        .target
+        .ok_or_else(|| anyhow::anyhow!("image {reference} has no target descriptor"))?;
+
+    let mut content = ContentClient::new(channel);
+    let bytes = read_blob(&mut content, namespace, &target.digest).await?;
+
+    let manifest_bytes = match target.media_type.as_str() {
+        "application/vnd.oci.image.index.v1+json"
+        | "application/vnd.docker.distribution.manifest.list.v2+json" => {
+            let index: ImageIndex = serde_json::from_slice(&bytes)?;
+            let arch = match consts::ARCH {
+                "x86_64" => "amd64",
+                "aarch64" => "arm64",
+                other => other,
+            };
+            let manifest = index
+                .manifests()
+                .iter()
+                .find(|m| {
+                    m.platform().as_ref().is_some_and(|p| {
+                        p.os().to_string() == "linux" && p.architecture().to_string() == arch
+                    })
+                })
+                .or_else(|| index.manifests().first())
+                .ok_or_else(|| anyhow::anyhow!("image index for {reference} has no manifests"))?;
+            read_blob(&mut content, namespace, &manifest.digest().to_string()).await?
+        }
+        _ => bytes,
+    };
+    let manifest: ImageManifest = serde_json::from_slice(&manifest_bytes)?;
+
+    let config_bytes = read_blob(&mut content, namespace, &manifest.config().digest().to_string()).await?;
+    Ok(serde_json::from_slice(&config_bytes)?)
+}
+
+/// Parse an OCI image config `User` field of the form `uid` or `uid:gid`
+/// into a runtime spec `User`. Named users/groups would require resolving
+/// `/etc/passwd` inside the image's rootfs, which this crate doesn't do
+/// yet, so they're left for containerd's default (root).
+fn numeric_user(user: Option<&str>) -> Option<oci_spec::runtime::User> {
+    let (uid, gid) = user?.split_once(':').unwrap_or((user?, "0"));
+    let uid: u32 = uid.parse().ok()?;
+    let gid: u32 = gid.parse().ok()?;
+    oci_spec::runtime::UserBuilder::default()
+        .uid(uid)
+        .gid(gid)
+        .build()
+        .ok()
+}
+
+/// Read a content-addressed blob from the content store in full.
+async fn read_blob(
+    client: &mut ContentClient<Channel>,
+    namespace: &str,
+    digest: &str,
+) -> anyhow::Result<Vec<u8>> {
+    let request = ReadContentRequest {
+        digest: digest.to_string(),
+        offset: 0,
+        size: 0,
+    };
+    let mut stream = client
+        .read(with_namespace!(request, namespace))
+        .await?
+        .into_inner();
+
+    let mut buf = Vec::new();
+    while let Some(chunk) = stream.message().await? {
+        buf.extend_from_slice(&chunk.data);
+    }
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_uid_only() {
+        let user = numeric_user(Some("1000")).unwrap();
+        assert_eq!(user.uid(), 1000);
+        assert_eq!(user.gid(), 0);
+    }
+
+    #[test]
+    fn parses_uid_and_gid() {
+        let user = numeric_user(Some("1000:1000")).unwrap();
+        assert_eq!(user.uid(), 1000);
+        assert_eq!(user.gid(), 1000);
+    }
+
+    #[test]
+    fn named_user_is_not_supported() {
+        assert!(numeric_user(Some("root")).is_none());
+        assert!(numeric_user(Some("root:root")).is_none());
+    }
+
+    #[test]
+    fn no_user_is_none() {
+        assert!(numeric_user(None).is_none());
+    }
+}
@@ -0,0 +1,141 @@
+use containerd_client::services::v1::tasks_client::TasksClient;
+use containerd_client::services::v1::{
+    CreateTaskRequest, DeleteTaskRequest, ExecProcessRequest, StartRequest, WaitRequest,
+};
+use containerd_client::with_namespace;
+use futures_util::Stream;
+use tonic::transport::Channel;
+
+use crate::exec::{self, Exec, ExecOptions};
+use crate::tty::{self, Fifos, TtyChunk};
+
+/// Handle to a container's task (its running process).
+///
+/// Obtained via [`crate::container::Container::tasks`].
+pub struct Task {
+    pub(crate) channel: Channel,
+    pub(crate) namespace: String,
+    pub(crate) container_id: String,
+}
+
+impl Task {
+    /// Create the task, wiring its stdio to the given file paths.
+    pub async fn create(
+        &self,
+        stdin: &str,
+        stdout: &str,
+        stderr: &str,
+        terminal: bool,
+    ) -> anyhow::Result<()> {
+        let mut client = TasksClient::new(self.channel.clone());
+        let request = CreateTaskRequest {
+            container_id: self.container_id.clone(),
+            stdin: stdin.to_string(),
+            stdout: stdout.to_string(),
+            stderr: stderr.to_string(),
+            terminal,
+            ..Default::default()
+        };
+        client
+            .create(with_namespace!(request, &self.namespace))
+            .await?;
+        Ok(())
+    }
+
+    /// Create the task with freshly allocated FIFOs and return a live stream
+    /// of its stdout/stderr, tagged by [`TtyChunk`], instead of writing to
+    /// plain files. Call [`Task::start`] afterwards to begin execution.
+    pub async fn create_attached(
+        &self,
+        terminal: bool,
+    ) -> anyhow::Result<impl Stream<Item = anyhow::Result<TtyChunk>>> {
+        let dir = std::env::temp_dir()
+            .join("kappa-fifos")
+            .join(&self.container_id);
+        std::fs::create_dir_all(&dir)?;
+        let fifos = Fifos::create(&dir, &self.container_id)?;
+
+        let mut client = TasksClient::new(self.channel.clone());
+        let request = CreateTaskRequest {
+            container_id: self.container_id.clone(),
+            stdin: fifos.stdin.to_string_lossy().into_owned(),
+            stdout: fifos.stdout.to_string_lossy().into_owned(),
+            stderr: fifos.stderr.to_string_lossy().into_owned(),
+            terminal,
+            ..Default::default()
+        };
+        client
+            .create(with_namespace!(request, &self.namespace))
+            .await?;
+
+        tty::open(fifos, terminal)
+    }
+
+    /// Create an additional process named `id` inside this already-running
+    /// task, e.g. to shell into a live container or run a health probe,
+    /// without restarting the container. The process does not run until
+    /// [`Exec::start`] is called on the returned handle.
+    pub async fn exec(&self, id: &str, options: ExecOptions) -> anyhow::Result<Exec> {
+        let spec = exec::process_spec(&options)?;
+
+        let mut client = TasksClient::new(self.channel.clone());
+        let request = ExecProcessRequest {
+            container_id: self.container_id.clone(),
+            exec_id: id.to_string(),
+            stdin: options.stdin,
+            stdout: options.stdout,
+            stderr: options.stderr,
+            terminal: options.terminal,
+            spec: Some(spec),
+        };
+        client
+            .exec(with_namespace!(request, &self.namespace))
+            .await?;
+
+        Ok(Exec {
+            channel: self.channel.clone(),
+            namespace: self.namespace.clone(),
+            container_id: self.container_id.clone(),
+            exec_id: id.to_string(),
+        })
+    }
+
+    /// Start the task running.
+    pub async fn start(&self) -> anyhow::Result<()> {
+        let mut client = TasksClient::new(self.channel.clone());
+        let request = StartRequest {
+            container_id: self.container_id.clone(),
+            ..Default::default()
+        };
+        client
+            .start(with_namespace!(request, &self.namespace))
+            .await?;
+        Ok(())
+    }
+
+    /// Block until the task exits, returning its exit status.
+    pub async fn wait(&self) -> anyhow::Result<u32> {
+        let mut client = TasksClient::new(self.channel.clone());
+        let request = WaitRequest {
+            container_id: self.container_id.clone(),
+            ..Default::default()
+        };
+        let response = client
+            .wait(with_namespace!(request, &self.namespace))
+            .await?;
+        Ok(response.into_inner().exit_status)
+    }
+
+    /// Delete the task. It must have already exited.
+    pub async fn delete(&self) -> anyhow::Result<()> {
+        let mut client = TasksClient::new(self.channel.clone());
+        let request = DeleteTaskRequest {
+            container_id: self.container_id.clone(),
+            ..Default::default()
+        };
+        client
+            .delete(with_namespace!(request, &self.namespace))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+//! Live stdio streaming for a task.
+//!
+//! containerd wires a task's stdio to named pipes (FIFOs) rather than
+//! sockets, so following a running task means creating those FIFOs
+//! ourselves, handing their paths to `CreateTaskRequest`, and then reading
+//! from our end as the shim writes to the other. This mirrors shiplift's
+//! TTY `Multiplexer`: output is yielded as a stream of [`TtyChunk`]s tagged
+//! by which stream they came from.
+
+use bytes::Bytes;
+use futures_util::future::Either;
+use futures_util::{Stream, StreamExt};
+use nix::sys::stat::Mode;
+use nix::unistd::mkfifo;
+use std::path::{Path, PathBuf};
+use tokio::net::unix::pipe;
+use tokio_util::io::ReaderStream;
+
+/// A chunk of output read from a task's stdio FIFOs.
+#[derive(Debug, Clone)]
+pub enum TtyChunk {
+    /// Data from stdout, or the combined stream when the task has a terminal.
+    StdOut(Bytes),
+    /// Data from stderr. Never produced when the task has a terminal.
+    StdErr(Bytes),
+}
+
+/// The FIFO paths a task's stdio is wired to.
+pub(crate) struct Fifos {
+    pub stdin: PathBuf,
+    pub stdout: PathBuf,
+    pub stderr: PathBuf,
+}
+
+impl Fifos {
+    /// Create a fresh set of named pipes for `container_id` under `dir`,
+    /// removing any left over from a previous `create_attached` call for
+    /// the same id first.
+    pub(crate) fn create(dir: &Path, container_id: &str) -> anyhow::Result<Self> {
+        let fifos = Self {
+            stdin: dir.join(format!("{container_id}-stdin")),
+            stdout: dir.join(format!("{container_id}-stdout")),
+            stderr: dir.join(format!("{container_id}-stderr")),
+        };
+        for path in [&fifos.stdin, &fifos.stdout, &fifos.stderr] {
+            if let Err(err) = std::fs::remove_file(path) {
+                if err.kind() != std::io::ErrorKind::NotFound {
+                    return Err(err.into());
+                }
+            }
+            mkfifo(path, Mode::S_IRWXU)?;
+        }
+        Ok(fifos)
+    }
+}
+
+impl Drop for Fifos {
+    fn drop(&mut self) {
+        for path in [&self.stdin, &self.stdout, &self.stderr] {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Open a task's stdout/stderr FIFOs and return a stream of tagged chunks.
+///
+/// When `terminal` is true, containerd combines both streams onto `stdout`
+/// and `stderr` is never opened; every chunk is tagged [`TtyChunk::StdOut`].
+/// Otherwise both are opened and interleaved as they arrive. Takes `fifos`
+/// by value and keeps it alive for as long as the returned stream is, so
+/// the FIFO files are cleaned up once the caller is done reading.
+pub(crate) fn open(
+    fifos: Fifos,
+    terminal: bool,
+) -> anyhow::Result<impl Stream<Item = anyhow::Result<TtyChunk>>> {
+    let out_stream = ReaderStream::new(pipe::OpenOptions::new().open_receiver(&fifos.stdout)?)
+        .map(|chunk| Ok(TtyChunk::StdOut(chunk?)));
+
+    let combined = if terminal {
+        Either::Left(out_stream)
+    } else {
+        let err_stream = ReaderStream::new(pipe::OpenOptions::new().open_receiver(&fifos.stderr)?)
+            .map(|chunk| Ok(TtyChunk::StdErr(chunk?)));
+        Either::Right(futures_util::stream::select(out_stream, err_stream))
+    };
+
+    Ok(combined.scan(fifos, |_fifos, item| async move { Some(item) }))
+}